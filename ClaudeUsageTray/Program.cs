@@ -9,7 +9,11 @@ using System.Windows.Forms;
 
 namespace ClaudeUsageTray;
 
-record UsageSection(string Name, int Percent, string ResetText, double ResetMinutes);
+record UsageSection(string Name, int Percent, string ResetText, string ResetAbsolute, double ResetMinutes, double WindowMinutes, bool Blocked);
+
+record OAuthCreds(string AccessToken, string RefreshToken, string SubscriptionType, long ExpiresAt);
+
+record CachedUsageResponse(string Body, EntityTagHeaderValue? ETag, DateTimeOffset? LastModified);
 
 static class Program
 {
@@ -60,12 +64,24 @@ sealed class TrayContext : ApplicationContext
         Environment.GetFolderPath(Environment.SpecialFolder.UserProfile),
         ".claude", ".credentials.json");
 
+    private static readonly string ClaudeSettingsPath = Path.Combine(
+        Environment.GetFolderPath(Environment.SpecialFolder.UserProfile),
+        ".claude", "settings.json");
+
     private const string UsageUrl = "https://api.anthropic.com/api/oauth/usage";
     private const string TokenUrl = "https://api.anthropic.com/v1/oauth/token";
     private const string ClientId = "9d1c250a-e61b-44d9-88ed-5944d1962f5e";
 
+    // Header values can be overridden when the API moves on before a new release ships
+    private static readonly string UserAgent = EnvOrDefault("CLAUDE_USER_AGENT", "claude-code/2.1.69");
+    private static readonly string OAuthBeta = EnvOrDefault("CLAUDE_OAUTH_BETA", "oauth-2025-04-20");
+
     private static readonly HttpClient Http = new();
 
+    // Last good usage response and its validators, for conditional requests. Fetches can overlap,
+    // so body and validators are swapped together as one reference and read once per request.
+    private static CachedUsageResponse? _usageCache;
+
     private readonly NotifyIcon _icon = null!;
     private readonly System.Windows.Forms.Timer _timer;
     private UsagePopup? _popup;
@@ -74,9 +90,17 @@ sealed class TrayContext : ApplicationContext
     private string _resetDate = "—";
     private int _usedPercent;
     private double _resetMinutes;
+    private double _windowMinutes = 300;
     private string _lastUpdated = "never";
     private List<UsageSection> _sections = new();
+    private List<string> _warnings = new();
+    private string? _lastRawJson;
+    private string _lastGoodPlanName = "";
+    private DateTimeOffset _lastGoodAt;
+    private string? _model;
     private int _backoffMs = 60_000;
+    private bool _paused;
+    private string _tooltip = "Claude Usage";
 
     private const string StartupRegKey = @"Software\Microsoft\Windows\CurrentVersion\Run";
     private const string StartupRegName = "ClaudeUsageTray";
@@ -84,14 +108,28 @@ sealed class TrayContext : ApplicationContext
     private static readonly string[] IconStyleNames = ["Circle", "Rectangle", "Fill", "Split", "Timer"];
     private int _iconStyle = 2; // 0 = circle, 1 = rectangle, 2 = fill, 3 = split, 4 = timer
 
+    private static readonly string[] PrimarySectionNames = ["Auto", "Current session", "All models", "Opus only", "Sonnet only"];
+    private int _primarySection; // 0 = auto (session, then all models), otherwise a section name
+
+    private static readonly string[] SectionOrderNames = ["Default", "Current session", "All models", "Opus only", "Sonnet only"];
+    private int _sectionOrder; // 0 = API order, otherwise the named section is listed first
+
     public TrayContext()
     {
         var menu = new ContextMenuStrip();
         menu.Items.Add("Refresh", null, (_, _) => FetchUsage());
         menu.Items.Add($"Icon: {IconStyleNames[_iconStyle]}", null, (_, _) => CycleIconStyle(menu));
+        menu.Items.Add($"Tray shows: {PrimarySectionNames[_primarySection]}", null, (_, _) => CyclePrimarySection(menu));
+        menu.Items.Add($"List first: {SectionOrderNames[_sectionOrder]}", null, (_, _) => CycleSectionOrder(menu));
         var startupItem = new ToolStripMenuItem("Run at startup") { Checked = IsStartupEnabled() };
         startupItem.Click += (_, _) => { var on = !startupItem.Checked; SetStartupEnabled(on); startupItem.Checked = on; };
         menu.Items.Add(startupItem);
+        var pauseItem = new ToolStripMenuItem("Pause updates");
+        pauseItem.Click += (_, _) => { SetPaused(!_paused); pauseItem.Checked = _paused; };
+        menu.Items.Add(pauseItem);
+        menu.Items.Add("Copy usage", null, (_, _) => CopyUsage());
+        menu.Items.Add("Append to CSV...", null, (_, _) => ExportUsageCsv());
+        menu.Items.Add("Copy raw response", null, (_, _) => { if (_lastRawJson != null) Clipboard.SetText(_lastRawJson); });
         menu.Items.Add(new ToolStripSeparator());
         menu.Items.Add("Exit", null, (_, _) => { _icon.Visible = false; Application.Exit(); });
 
@@ -112,9 +150,18 @@ sealed class TrayContext : ApplicationContext
         _timer.Tick += (_, _) => FetchUsage();
         _timer.Start();
 
+        Microsoft.Win32.SystemEvents.PowerModeChanged += OnPowerModeChanged;
+
         FetchUsage();
     }
 
+    private void OnPowerModeChanged(object sender, Microsoft.Win32.PowerModeChangedEventArgs e)
+    {
+        if (e.Mode != Microsoft.Win32.PowerModes.Resume) return;
+        // Give the network a moment to come back before fetching, otherwise the first request after wake fails
+        Task.Delay(15_000).ContinueWith(_ => InvokeOnUI(FetchUsage));
+    }
+
     private void TogglePopup()
     {
         if (_popup is { Visible: true })
@@ -123,39 +170,137 @@ sealed class TrayContext : ApplicationContext
             _popup = null;
             return;
         }
-        _popup = new UsagePopup(_planName, _resetDate, _sections, _lastUpdated, FetchUsage);
+        _popup = new UsagePopup(_planName, _model, _resetDate, OrderedSections(), _warnings, _lastUpdated, FetchUsage);
         _popup.Show();
     }
 
-    private void ApplyUsageData(string planName, List<UsageSection> sections)
+    private void ApplyUsageData(string planName, List<UsageSection> sections, List<string> warnings)
     {
         _planName = planName;
         _sections = sections;
+        _warnings = warnings;
+        _lastUpdated = DateTime.Now.ToString("h:mm tt");
+        _lastGoodPlanName = planName;
+        _lastGoodAt = DateTimeOffset.Now;
+        UpdatePrimary();
+    }
+
+    private UsageSection? FindPrimarySection()
+    {
+        if (_primarySection > 0)
+        {
+            // A chosen section missing from this response falls back to whichever is closest to its cap
+            return _sections.Find(s => s.Name == PrimarySectionNames[_primarySection])
+                   ?? _sections.MaxBy(s => s.Percent);
+        }
 
-        var primary = sections.Find(s => s.Name == "Current session")
-                      ?? sections.Find(s => s.Name == "All models")
-                      ?? (sections.Count > 0 ? sections[0] : null);
+        return _sections.Find(s => s.Name == "Current session")
+               ?? _sections.Find(s => s.Name == "All models")
+               ?? (_sections.Count > 0 ? _sections[0] : null);
+    }
+
+    private void UpdatePrimary()
+    {
+        var primary = FindPrimarySection();
 
         _usedPercent = primary?.Percent ?? 0;
         _resetMinutes = primary?.ResetMinutes ?? 0;
-        _resetDate = primary?.ResetText ?? "";
-        _lastUpdated = DateTime.Now.ToString("h:mm tt");
+        _windowMinutes = primary?.WindowMinutes ?? 300;
+        _resetDate = primary == null ? "" : WithAbsolute(primary.ResetText, primary.ResetAbsolute);
 
         _icon.Icon?.Dispose();
         _icon.Icon = MakeIcon(_usedPercent);
-        var tip = $"{_planName} — {_usedPercent}% used\nResets: {_resetDate}";
-        _icon.Text = tip.Length > 127 ? tip[..127] : tip;
+        // Same rule as the popup: with no future reset time, "blocked until reset" says it all
+        SetTooltip(primary?.Blocked == true
+            ? primary.ResetText is "" or "now"
+                ? $"{_planName} — blocked until reset"
+                : $"{_planName} — blocked until reset\nResets: {_resetDate}"
+            : $"{_planName} — {_usedPercent}% used\nResets: {_resetDate}");
     }
 
     private void ShowError(string message)
     {
         _planName = "Error";
-        var tip = $"Error: {message}";
+        SetTooltip($"Error: {message}");
+    }
+
+    private void SetTooltip(string tip)
+    {
+        // Remember the unsuffixed text so pausing/unpausing can re-render it
+        _tooltip = tip;
+        if (_paused)
+            tip += "\nUpdates paused";
         _icon.Text = tip.Length > 127 ? tip[..127] : tip;
     }
 
+    // Exports always use the last successful snapshot (its own plan and timestamp), even while
+    // a later fetch is failing and the tray shows an error
+    private bool CanExportUsage()
+    {
+        if (_sections.Count > 0) return true;
+        MessageBox.Show("No usage data has been fetched yet. Refresh and try again.",
+            "Usage unavailable", MessageBoxButtons.OK, MessageBoxIcon.Information);
+        return false;
+    }
+
+    private void CopyUsage()
+    {
+        if (!CanExportUsage()) return;
+        var json = JsonSerializer.Serialize(new
+        {
+            plan = _lastGoodPlanName,
+            updated_at = _lastGoodAt.ToString("o"),
+            sections = OrderedSections().Select(s => new { name = s.Name, percent = s.Percent, reset_minutes = Math.Round(s.ResetMinutes) })
+        }, new JsonSerializerOptions { WriteIndented = true });
+        Clipboard.SetText(json);
+    }
+
+    private void ExportUsageCsv()
+    {
+        if (!CanExportUsage()) return;
+        using var dialog = new SaveFileDialog
+        {
+            Title = "Append usage to CSV",
+            Filter = "CSV files (*.csv)|*.csv",
+            FileName = "claude-usage.csv",
+            OverwritePrompt = false
+        };
+        if (dialog.ShowDialog() != DialogResult.OK) return;
+
+        // One row per section so a spreadsheet can pivot on section name
+        var lines = new List<string>();
+        if (!File.Exists(dialog.FileName))
+            lines.Add("timestamp,plan,section,percent,reset_minutes");
+        var timestamp = _lastGoodAt.ToString("o");
+        foreach (var sec in OrderedSections())
+            lines.Add($"{timestamp},{_lastGoodPlanName},{sec.Name},{sec.Percent},{Math.Round(sec.ResetMinutes)}");
+        try { File.AppendAllLines(dialog.FileName, lines); }
+        catch (Exception ex) when (ex is IOException or UnauthorizedAccessException) { MessageBox.Show(ex.Message, "Export failed", MessageBoxButtons.OK, MessageBoxIcon.Warning); }
+    }
+
+    private void SetPaused(bool paused)
+    {
+        _paused = paused;
+        SetTooltip(_tooltip);
+        if (paused)
+        {
+            _timer.Stop();
+        }
+        else
+        {
+            _timer.Start();
+            FetchUsage();
+        }
+    }
+
     private void FetchUsage()
     {
+        // While paused, keep showing the last fetched data without touching the network
+        if (_paused) return;
+
+        // A fetch that finishes after the user paused is dropped so the frozen data stays frozen
+        void Deliver(Action action) => InvokeOnUI(() => { if (!_paused) action(); });
+
         Task.Run(async () =>
         {
             try
@@ -170,21 +315,33 @@ sealed class TrayContext : ApplicationContext
                         throw new Exception("Auth failed. Run \"claude auth\".");
                 }
 
-                var (planName, sections) = TransformUsageData(raw.Value, subscriptionType);
+                var (planName, sections, warnings) = TransformUsageData(raw.Value, subscriptionType);
                 _backoffMs = 5 * 60_000;
                 _timer.Interval = 5 * 60_000;
-                InvokeOnUI(() => ApplyUsageData(planName, sections));
+                var rawJson = JsonSerializer.Serialize(raw.Value, new JsonSerializerOptions { WriteIndented = true });
+                var model = await ReadClaudeModelAsync();
+                Deliver(() =>
+                {
+                    _lastRawJson = rawJson;
+                    _model = model;
+                    ApplyUsageData(planName, sections, warnings);
+                });
             }
             catch (HttpRequestException ex) when (ex.StatusCode == System.Net.HttpStatusCode.TooManyRequests)
             {
                 _backoffMs = Math.Min(Math.Max(_backoffMs * 2, 5 * 60_000), 40 * 60_000);
                 _timer.Interval = _backoffMs;
                 if (_sections.Count == 0)
-                    InvokeOnUI(() => ShowError($"Rate limited — retrying in {_backoffMs / 60_000}m"));
+                    Deliver(() => ShowError($"Rate limited — retrying in {_backoffMs / 60_000}m"));
+            }
+            catch (Exception ex) when (ex is HttpRequestException { StatusCode: null } or TaskCanceledException)
+            {
+                // No HTTP status means we never reached the API; don't send the user off to re-auth
+                Deliver(() => ShowError("Offline — will retry"));
             }
             catch (Exception ex)
             {
-                InvokeOnUI(() => ShowError(ex.Message));
+                Deliver(() => ShowError(ex.Message));
             }
         });
     }
@@ -197,32 +354,102 @@ sealed class TrayContext : ApplicationContext
             action();
     }
 
+    // The claude CLI may be rewriting the file at the same moment; sharing violations clear up within a few ms
+    private static async Task<string> ReadCredentialsFileAsync()
+    {
+        for (int attempt = 0; ; attempt++)
+        {
+            try { return await File.ReadAllTextAsync(CredentialsPath); }
+            catch (IOException ex) when (attempt < 3 && IsFileLocked(ex)) { await Task.Delay(100 * (attempt + 1)); }
+        }
+    }
+
+    private static async Task WriteCredentialsFileAsync(string contents)
+    {
+        for (int attempt = 0; ; attempt++)
+        {
+            try { await File.WriteAllTextAsync(CredentialsPath, contents); return; }
+            catch (IOException ex) when (attempt < 3 && IsFileLocked(ex)) { await Task.Delay(100 * (attempt + 1)); }
+        }
+    }
+
+    private static bool IsFileLocked(IOException ex)
+    {
+        const int ErrorSharingViolation = 32;
+        const int ErrorLockViolation = 33;
+        var code = ex.HResult & 0xFFFF;
+        return code is ErrorSharingViolation or ErrorLockViolation;
+    }
+
+    private static async Task<OAuthCreds> ReadCredentialsAsync()
+    {
+        using var doc = JsonDocument.Parse(await ReadCredentialsFileAsync());
+        var root = doc.RootElement;
+
+        // Current CLI format: camelCase keys under a "claudeAiOauth" wrapper
+        if (root.TryGetProperty("claudeAiOauth", out var oauth)
+            && TryReadCredentials(oauth, "accessToken", "refreshToken", "subscriptionType", "expiresAt", out var creds))
+            return creds;
+
+        // Legacy CLI format: snake_case keys at the top level
+        if (TryReadCredentials(root, "access_token", "refresh_token", "subscription_type", "expires_at", out creds))
+            return creds;
+
+        throw new Exception("Unrecognized credentials format. Run \"claude auth\".");
+    }
+
+    private static bool TryReadCredentials(JsonElement el, string accessKey, string refreshKey, string subKey, string expiresKey, out OAuthCreds creds)
+    {
+        creds = null!;
+        if (el.ValueKind != JsonValueKind.Object
+            || !el.TryGetProperty(accessKey, out var at) || at.ValueKind != JsonValueKind.String
+            || !el.TryGetProperty(refreshKey, out var rt) || rt.ValueKind != JsonValueKind.String)
+            return false;
+
+        var sub = el.TryGetProperty(subKey, out var st) ? st.GetString() ?? "unknown" : "unknown";
+        var expiresAt = el.TryGetProperty(expiresKey, out var ea) && ea.ValueKind == JsonValueKind.Number ? ea.GetInt64() : 0;
+        creds = new OAuthCreds(at.GetString()!, rt.GetString()!, sub, expiresAt);
+        return true;
+    }
+
+    private static async Task<string?> ReadClaudeModelAsync()
+    {
+        // Optional: Claude Code only writes "model" here once the user picks one
+        try
+        {
+            if (!File.Exists(ClaudeSettingsPath))
+                return null;
+            using var doc = JsonDocument.Parse(await File.ReadAllTextAsync(ClaudeSettingsPath));
+            return doc.RootElement.ValueKind == JsonValueKind.Object
+                   && doc.RootElement.TryGetProperty("model", out var m) && m.ValueKind == JsonValueKind.String
+                ? m.GetString()
+                : null;
+        }
+        catch (Exception ex) when (ex is IOException or JsonException or UnauthorizedAccessException)
+        {
+            return null;
+        }
+    }
+
     private static async Task<(string accessToken, string subscriptionType)> GetAccessTokenAsync()
     {
         if (!File.Exists(CredentialsPath))
-            throw new Exception($"No credentials. Run \"claude auth\".");
-
-        using var doc = JsonDocument.Parse(await File.ReadAllTextAsync(CredentialsPath));
-        var oauth = doc.RootElement.GetProperty("claudeAiOauth");
-        var token = oauth.GetProperty("accessToken").GetString()!;
-        var refreshToken = oauth.GetProperty("refreshToken").GetString()!;
-        var sub = oauth.TryGetProperty("subscriptionType", out var st) ? st.GetString() ?? "unknown" : "unknown";
-        var expiresAt = oauth.TryGetProperty("expiresAt", out var ea) ? ea.GetInt64() : 0;
+            throw new Exception(IsClaudeCliInstalled()
+                ? "No credentials. Run \"claude auth\"."
+                : "Claude Code not found. Install it, then run \"claude auth\".");
 
-        if (expiresAt > 0 && DateTimeOffset.UtcNow.ToUnixTimeMilliseconds() > expiresAt - 60000)
-            return await RefreshAndGetTokenAsync(refreshToken, sub);
+        var creds = await ReadCredentialsAsync();
+        if (creds.ExpiresAt > 0 && DateTimeOffset.UtcNow.ToUnixTimeMilliseconds() > creds.ExpiresAt - 60000)
+            return await RefreshAndGetTokenAsync(creds.RefreshToken, creds.SubscriptionType);
 
-        return (token, sub);
+        return (creds.AccessToken, creds.SubscriptionType);
     }
 
     private static async Task<(string accessToken, string subscriptionType)> RefreshAndGetTokenAsync()
     {
         // Overload that reads credentials from file (used on auth failure retry)
-        using var doc = JsonDocument.Parse(await File.ReadAllTextAsync(CredentialsPath));
-        var oauth = doc.RootElement.GetProperty("claudeAiOauth");
-        var refreshToken = oauth.GetProperty("refreshToken").GetString()!;
-        var sub = oauth.TryGetProperty("subscriptionType", out var st) ? st.GetString() ?? "unknown" : "unknown";
-        return await RefreshAndGetTokenAsync(refreshToken, sub);
+        var creds = await ReadCredentialsAsync();
+        return await RefreshAndGetTokenAsync(creds.RefreshToken, creds.SubscriptionType);
     }
 
     private static async Task<(string accessToken, string subscriptionType)> RefreshAndGetTokenAsync(string refreshToken, string subscriptionType)
@@ -234,9 +461,36 @@ sealed class TrayContext : ApplicationContext
             client_id = ClientId
         });
 
-        var resp = await Http.PostAsync(TokenUrl, new StringContent(body, System.Text.Encoding.UTF8, "application/json"));
-        if (!resp.IsSuccessStatusCode)
+        // Retry transport failures, 408/429 and 5xx a couple of times; only 400/401/403 mean the refresh token was rejected
+        HttpResponseMessage resp;
+        for (int attempt = 0; ; attempt++)
+        {
+            var delay = TimeSpan.FromSeconds(attempt + 1);
+            try
+            {
+                resp = await Http.PostAsync(TokenUrl, new StringContent(body, System.Text.Encoding.UTF8, "application/json"));
+                if (!IsTransientStatus(resp.StatusCode) || attempt >= 2)
+                    break;
+
+                // Honour Retry-After; if the server wants longer than we'll block a fetch for, leave it to the next tick
+                var retryAfter = resp.Headers.RetryAfter?.Delta
+                                 ?? (resp.Headers.RetryAfter?.Date is { } date ? (TimeSpan?)(date - DateTimeOffset.UtcNow) : null);
+                if (retryAfter > TimeSpan.FromSeconds(10))
+                    break;
+                if (retryAfter > delay)
+                    delay = retryAfter.Value;
+                resp.Dispose();
+            }
+            catch (Exception ex) when (attempt < 2 && ex is HttpRequestException or TaskCanceledException) { }
+            await Task.Delay(delay);
+        }
+
+        if (resp.StatusCode is System.Net.HttpStatusCode.BadRequest or System.Net.HttpStatusCode.Unauthorized or System.Net.HttpStatusCode.Forbidden)
             throw new Exception($"Token refresh failed ({(int)resp.StatusCode}). Run \"claude auth\".");
+        if (IsTransientStatus(resp.StatusCode))
+            throw new Exception($"Token refresh failed ({(int)resp.StatusCode}). Will retry.");
+        if (!resp.IsSuccessStatusCode)
+            throw new Exception($"Token refresh failed ({(int)resp.StatusCode}).");
 
         var tokens = JsonDocument.Parse(await resp.Content.ReadAsStringAsync());
         var newAccess = tokens.RootElement.GetProperty("access_token").GetString()!;
@@ -244,61 +498,135 @@ sealed class TrayContext : ApplicationContext
         var expiresIn = tokens.RootElement.GetProperty("expires_in").GetInt64();
 
         // Update credentials file
-        var node = JsonNode.Parse(await File.ReadAllTextAsync(CredentialsPath))!;
-        var oauthNode = node["claudeAiOauth"]!;
-        oauthNode["accessToken"] = newAccess;
-        oauthNode["refreshToken"] = newRefresh;
-        oauthNode["expiresAt"] = DateTimeOffset.UtcNow.ToUnixTimeMilliseconds() + expiresIn * 1000;
-        await File.WriteAllTextAsync(CredentialsPath, node.ToJsonString());
+        var node = JsonNode.Parse(await ReadCredentialsFileAsync())!;
+        var newExpiresAt = DateTimeOffset.UtcNow.ToUnixTimeMilliseconds() + expiresIn * 1000;
+        if (node["claudeAiOauth"] is JsonObject oauthNode)
+        {
+            oauthNode["accessToken"] = newAccess;
+            oauthNode["refreshToken"] = newRefresh;
+            oauthNode["expiresAt"] = newExpiresAt;
+        }
+        else
+        {
+            // Keep legacy files in their original shape
+            node["access_token"] = newAccess;
+            node["refresh_token"] = newRefresh;
+            node["expires_at"] = newExpiresAt;
+        }
+        await WriteCredentialsFileAsync(node.ToJsonString());
 
         return (newAccess, subscriptionType);
     }
 
+    private static bool IsTransientStatus(System.Net.HttpStatusCode status) =>
+        status is System.Net.HttpStatusCode.RequestTimeout or System.Net.HttpStatusCode.TooManyRequests || (int)status >= 500;
+
     private static async Task<JsonElement?> FetchUsageApiAsync(string accessToken)
     {
         var req = new HttpRequestMessage(HttpMethod.Get, UsageUrl);
         req.Headers.Authorization = new AuthenticationHeaderValue("Bearer", accessToken);
-        req.Headers.Add("anthropic-beta", "oauth-2025-04-20");
-        req.Headers.Add("User-Agent", "claude-code/2.1.69");
+        req.Headers.TryAddWithoutValidation("anthropic-beta", OAuthBeta);
+        req.Headers.TryAddWithoutValidation("User-Agent", UserAgent);
         req.Headers.Accept.Add(new MediaTypeWithQualityHeaderValue("application/json"));
+        var cache = Volatile.Read(ref _usageCache);
+        if (cache != null)
+        {
+            if (cache.ETag != null) req.Headers.IfNoneMatch.Add(cache.ETag);
+            if (cache.LastModified != null) req.Headers.IfModifiedSince = cache.LastModified;
+        }
 
         var resp = await Http.SendAsync(req);
         if (resp.StatusCode is System.Net.HttpStatusCode.Unauthorized or System.Net.HttpStatusCode.Forbidden)
             return null;
         if (resp.StatusCode == (System.Net.HttpStatusCode)429)
             throw new HttpRequestException("429", null, System.Net.HttpStatusCode.TooManyRequests);
+        if (resp.StatusCode == System.Net.HttpStatusCode.NotModified && cache != null)
+        {
+            using var cached = JsonDocument.Parse(cache.Body);
+            return cached.RootElement.Clone();
+        }
         if (!resp.IsSuccessStatusCode)
             throw new Exception($"Usage API error ({(int)resp.StatusCode})");
 
-        var doc = JsonDocument.Parse(await resp.Content.ReadAsStringAsync());
+        var body = await resp.Content.ReadAsStringAsync();
+        var doc = JsonDocument.Parse(body);
+
+        // The API sometimes reports an expired token as a 200 with an error body; treat it like a 401
+        if (doc.RootElement.ValueKind == JsonValueKind.Object
+            && doc.RootElement.TryGetProperty("error", out var err) && err.ValueKind == JsonValueKind.Object
+            && err.TryGetProperty("type", out var type) && type.ValueKind == JsonValueKind.String
+            && type.GetString() is "authentication_error" or "permission_error")
+            return null;
+
+        Volatile.Write(ref _usageCache, new CachedUsageResponse(body, resp.Headers.ETag, resp.Content.Headers.LastModified));
         return doc.RootElement.Clone();
     }
 
-    private static (string planName, List<UsageSection> sections) TransformUsageData(JsonElement raw, string subscriptionType)
+    // Field names the API has used, or may use, for the used percentage; checked in order.
+    // Overridable so a field rename can be worked around without a new release.
+    private static readonly string[] UsedPercentFields = EnvOrDefault("CLAUDE_USAGE_PERCENT_FIELDS", "utilization,percent_used,used_percent")
+        .Split(',', StringSplitOptions.RemoveEmptyEntries | StringSplitOptions.TrimEntries);
+
+    private static (string planName, List<UsageSection> sections, List<string> warnings) TransformUsageData(JsonElement raw, string subscriptionType)
     {
         var planName = char.ToUpper(subscriptionType[0]) + subscriptionType[1..] + " Plan";
         var sections = new List<UsageSection>();
+        var warnings = new List<string>();
 
-        void TryAdd(string prop, string name)
+        void TryAdd(string prop, string name, double windowMinutes)
         {
-            if (raw.TryGetProperty(prop, out var el) && el.ValueKind == JsonValueKind.Object)
+            if (!raw.TryGetProperty(prop, out var el) || el.ValueKind == JsonValueKind.Null)
+                return;
+            if (el.ValueKind != JsonValueKind.Object)
             {
-                var pct = el.TryGetProperty("utilization", out var u) && u.ValueKind == JsonValueKind.Number ? (int)Math.Round(u.GetDouble()) : 0;
-                var resetsAtStr = el.TryGetProperty("resets_at", out var r) && r.ValueKind == JsonValueKind.String ? r.GetString() : null;
-                var resetText = FormatResetTime(resetsAtStr);
-                double resetMinutes = 0;
-                if (!string.IsNullOrEmpty(resetsAtStr) && DateTimeOffset.TryParse(resetsAtStr, null, System.Globalization.DateTimeStyles.RoundtripKind, out var resetDto))
-                    resetMinutes = Math.Max(0, (resetDto.UtcDateTime - DateTime.UtcNow).TotalMinutes);
-                sections.Add(new UsageSection(name, pct, resetText, resetMinutes));
+                warnings.Add($"{name}: unexpected {el.ValueKind.ToString().ToLowerInvariant()} value");
+                return;
             }
+
+            int? pct = null;
+            var unreadableFields = new List<string>();
+            foreach (var field in UsedPercentFields)
+            {
+                if (!el.TryGetProperty(field, out var u) || u.ValueKind == JsonValueKind.Null)
+                    continue;
+                if (u.ValueKind == JsonValueKind.Number)
+                {
+                    pct = (int)Math.Round(u.GetDouble());
+                    break;
+                }
+                unreadableFields.Add(field);
+            }
+            // Fall back to a "remaining" percentage, inverted to match the used-percent convention
+            if (pct == null && el.TryGetProperty("remaining", out var rem) && rem.ValueKind == JsonValueKind.Number)
+                pct = (int)Math.Round(100 - rem.GetDouble());
+            // Only warn when nothing produced a percent; without this the section would show a confident 0%
+            if (pct == null)
+            {
+                if (unreadableFields.Count > 0)
+                    warnings.AddRange(unreadableFields.Select(field => $"{name}: unreadable {field}"));
+                else
+                    warnings.Add($"{name}: no usage percent");
+            }
+            var resetsAtStr = el.TryGetProperty("resets_at", out var r) && r.ValueKind == JsonValueKind.String ? r.GetString() : null;
+            var resetText = FormatResetTime(resetsAtStr);
+            var resetAbsolute = FormatResetAbsolute(resetsAtStr);
+            double resetMinutes = 0;
+            if (!string.IsNullOrEmpty(resetsAtStr) && DateTimeOffset.TryParse(resetsAtStr, null, System.Globalization.DateTimeStyles.RoundtripKind, out var resetDto))
+                resetMinutes = Math.Max(0, (resetDto.UtcDateTime - DateTime.UtcNow).TotalMinutes);
+            // Still at the cap means still blocked, even if resets_at is missing or already passed
+            // (the API hasn't rolled the window over yet)
+            var blocked = pct >= 100;
+            sections.Add(new UsageSection(name, pct ?? 0, resetText, resetAbsolute, resetMinutes, windowMinutes, blocked));
         }
 
-        TryAdd("five_hour", "Current session");
-        TryAdd("seven_day", "All models");
-        TryAdd("seven_day_opus", "Opus only");
-        TryAdd("seven_day_sonnet", "Sonnet only");
+        const double fiveHours = 5 * 60;
+        const double sevenDays = 7 * 24 * 60;
+        TryAdd("five_hour", "Current session", fiveHours);
+        TryAdd("seven_day", "All models", sevenDays);
+        TryAdd("seven_day_opus", "Opus only", sevenDays);
+        TryAdd("seven_day_sonnet", "Sonnet only", sevenDays);
 
-        return (planName, sections);
+        return (planName, sections, warnings);
     }
 
     private static string FormatResetTime(string? resetsAt)
@@ -312,6 +640,36 @@ sealed class TrayContext : ApplicationContext
         return hours > 0 ? $"in {hours}h {mins}m" : $"in {mins}m";
     }
 
+    private static bool IsClaudeCliInstalled()
+    {
+        // npm installs a claude.cmd shim; the native installer drops claude.exe
+        var dirs = (Environment.GetEnvironmentVariable("PATH") ?? "").Split(Path.PathSeparator, StringSplitOptions.RemoveEmptyEntries);
+        return dirs.Any(dir => new[] { "claude.exe", "claude.cmd" }.Any(name =>
+        {
+            try { return File.Exists(Path.Combine(dir.Trim('"'), name)); }
+            catch (ArgumentException) { return false; }
+        }));
+    }
+
+    private static string EnvOrDefault(string name, string fallback)
+    {
+        var value = Environment.GetEnvironmentVariable(name);
+        return string.IsNullOrWhiteSpace(value) ? fallback : value.Trim();
+    }
+
+    private static string FormatResetAbsolute(string? resetsAt)
+    {
+        if (string.IsNullOrEmpty(resetsAt) || !DateTimeOffset.TryParse(resetsAt, null, System.Globalization.DateTimeStyles.RoundtripKind, out var resetOfs))
+            return "";
+        if (resetOfs.UtcDateTime <= DateTime.UtcNow) return "";
+        // "t" follows the user's regional short-time format, including the 12h/24h choice
+        var local = resetOfs.ToLocalTime();
+        return local.Date == DateTime.Today ? local.ToString("t") : local.ToString("ddd ") + local.ToString("t");
+    }
+
+    internal static string WithAbsolute(string resetText, string resetAbsolute) =>
+        string.IsNullOrEmpty(resetAbsolute) ? resetText : $"{resetText} ({resetAbsolute})";
+
     private static bool IsStartupEnabled()
     {
         using var key = Microsoft.Win32.Registry.CurrentUser.OpenSubKey(StartupRegKey);
@@ -336,6 +694,28 @@ sealed class TrayContext : ApplicationContext
         _icon.Icon = MakeIcon(_usedPercent);
     }
 
+    private void CyclePrimarySection(ContextMenuStrip menu)
+    {
+        _primarySection = (_primarySection + 1) % PrimarySectionNames.Length;
+        menu.Items[2].Text = $"Tray shows: {PrimarySectionNames[_primarySection]}";
+        if (_sections.Count > 0)
+            UpdatePrimary();
+    }
+
+    private void CycleSectionOrder(ContextMenuStrip menu)
+    {
+        _sectionOrder = (_sectionOrder + 1) % SectionOrderNames.Length;
+        menu.Items[3].Text = $"List first: {SectionOrderNames[_sectionOrder]}";
+    }
+
+    private List<UsageSection> OrderedSections()
+    {
+        if (_sectionOrder == 0)
+            return _sections;
+        // Stable sort: the chosen section moves to the top, the rest keep their default order
+        return _sections.OrderBy(s => s.Name == SectionOrderNames[_sectionOrder] ? 0 : 1).ToList();
+    }
+
     [DllImport("user32.dll", SetLastError = true)]
     private static extern bool DestroyIcon(IntPtr hIcon);
 
@@ -343,8 +723,8 @@ sealed class TrayContext : ApplicationContext
     {
         1 => MakeRectangleIcon(percent),
         2 => MakeFillIcon(percent),
-        3 => MakeSplitIcon(percent, _resetMinutes),
-        4 => MakeTimerFillIcon(percent, _resetMinutes),
+        3 => MakeSplitIcon(percent, _resetMinutes, _windowMinutes),
+        4 => MakeTimerFillIcon(percent, _resetMinutes, _windowMinutes),
         _ => MakeCircleIcon(percent),
     };
 
@@ -434,14 +814,14 @@ sealed class TrayContext : ApplicationContext
         return BitmapToIcon(bmp);
     }
 
-    private static Icon MakeTimerFillIcon(int percent, double resetMinutes)
+    private static Icon MakeTimerFillIcon(int percent, double resetMinutes, double windowMinutes)
     {
         percent = Math.Clamp(percent, 0, 100);
         const int size = 32;
         float radius = size / 2f;
 
-        // Sweep angle: time remaining (5h/300min = 360°, 0min = 0°)
-        float timeFraction = (float)Math.Clamp(resetMinutes / 300.0, 0, 1);
+        // Sweep angle: time remaining (full window = 360°, 0min = 0°)
+        float timeFraction = (float)Math.Clamp(resetMinutes / windowMinutes, 0, 1);
         float sweepAngle = 360f * timeFraction;
 
         // Thickness: usage % (0% = nothing, 100% = solid pie wedge)
@@ -477,12 +857,12 @@ sealed class TrayContext : ApplicationContext
         return BitmapToIcon(bmp);
     }
 
-    private static Icon MakeSplitIcon(int percent, double resetMinutes)
+    private static Icon MakeSplitIcon(int percent, double resetMinutes, double windowMinutes)
     {
         // Top semicircle: usage % (fills from outside in, like Fill icon)
-        // Bottom semicircle: remaining time (0min = full, 300min/5h = empty)
+        // Bottom semicircle: remaining time (0min = full, full window = empty)
         percent = Math.Clamp(percent, 0, 100);
-        int timePct = Math.Clamp(100 - (int)(resetMinutes / 300.0 * 100), 0, 100);
+        int timePct = Math.Clamp(100 - (int)(resetMinutes / windowMinutes * 100), 0, 100);
 
         const int size = 32;
         float radius = size / 2f;
@@ -565,6 +945,7 @@ sealed class TrayContext : ApplicationContext
     {
         if (disposing)
         {
+            Microsoft.Win32.SystemEvents.PowerModeChanged -= OnPowerModeChanged;
             _popup?.Close();
             _timer.Dispose();
             _icon.Dispose();
@@ -582,7 +963,7 @@ sealed class UsagePopup : Form
     private int S(int value) => (int)(value * _dpi / 96f);
     private float S(float value) => value * _dpi / 96f;
 
-    public UsagePopup(string planName, string resetDate, List<UsageSection> sections, string lastUpdated, Action? onRefresh = null)
+    public UsagePopup(string planName, string? model, string resetDate, List<UsageSection> sections, List<string> warnings, string lastUpdated, Action? onRefresh = null)
     {
         FormBorderStyle = FormBorderStyle.None;
         StartPosition = FormStartPosition.Manual;
@@ -602,6 +983,13 @@ sealed class UsagePopup : Form
         Controls.Add(planLabel);
         y += S(30);
 
+        if (!string.IsNullOrEmpty(model))
+        {
+            var modelLabel = MakeLabel($"Model: {model}", S(12), y, S(276), Color.FromArgb(180, 170, 160), S(9f));
+            Controls.Add(modelLabel);
+            y += S(22);
+        }
+
         bool first = true;
         foreach (var sec in sections)
         {
@@ -615,14 +1003,31 @@ sealed class UsagePopup : Form
             first = false;
 
             y = AddProgressBar($"{sec.Name}: {sec.Percent}%", sec.Percent, y);
-            if (!string.IsNullOrEmpty(sec.ResetText))
+            if (sec.Blocked)
+            {
+                var text = sec.ResetText is "" or "now"
+                    ? "Blocked until reset"
+                    : $"Blocked — resets {TrayContext.WithAbsolute(sec.ResetText, sec.ResetAbsolute)}";
+                var bl = MakeLabel(text, S(12), y, S(276), Orange, S(7.5f), FontStyle.Bold);
+                Controls.Add(bl);
+                y += S(16);
+            }
+            else if (!string.IsNullOrEmpty(sec.ResetText))
             {
-                var rl = MakeLabel($"Resets {sec.ResetText}", S(12), y, S(276), Color.FromArgb(140, 130, 120), S(7.5f));
+                var rl = MakeLabel($"Resets {TrayContext.WithAbsolute(sec.ResetText, sec.ResetAbsolute)}", S(12), y, S(276), Color.FromArgb(140, 130, 120), S(7.5f));
                 Controls.Add(rl);
                 y += S(16);
             }
         }
 
+        // Flag sections that were dropped, or shown at 0% because their percent couldn't be read
+        foreach (var warning in warnings)
+        {
+            var wl = MakeLabel($"⚠ {warning}", S(12), y, S(276), Color.FromArgb(200, 160, 90), S(7.5f));
+            Controls.Add(wl);
+            y += S(16);
+        }
+
         y += S(8);
         var sep2 = new Panel { Location = new Point(S(12), y), Size = new Size(S(276), 1), BackColor = Color.FromArgb(60, 60, 56) };
         Controls.Add(sep2);